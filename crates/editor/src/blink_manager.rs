@@ -1,27 +1,85 @@
 use gpui::{Context, FocusHandle};
 use settings::SettingsStore;
 use smol::Timer;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use ui::{App, Window};
 
+/// Which part of the blink cycle the cursor is currently in, mirroring the
+/// `blinkwait`/`blinkon`/`blinkoff` model.
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum BlinkPhase {
+    /// The initial solid period before blinking begins.
+    Waiting,
+    /// The cursor is visible.
+    On,
+    /// The cursor is hidden.
+    Off,
+}
+
+/// The timing knobs for the solid-wait/on/off blink cycle, mirroring the
+/// `blinkwait`/`blinkon`/`blinkoff` model. Bundled into one struct so callers
+/// can't accidentally transpose same-typed `Duration` arguments.
+#[derive(Copy, Clone, Debug)]
+pub struct BlinkTimings {
+    /// How long the cursor stays solid before blinking begins. Zero skips the wait.
+    pub wait: Duration,
+    /// How long the cursor stays visible during a blink cycle. Zero disables blinking.
+    pub on: Duration,
+    /// How long the cursor stays hidden during a blink cycle. Zero disables blinking.
+    pub off: Duration,
+    /// How many visible/hidden cycles to blink through before settling solid.
+    /// Zero means unlimited.
+    pub max_cycles: u64,
+}
+
+/// How the cursor should be painted for the current focus/blink state.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum CursorDisplay {
+    /// Draw the cursor filled in.
+    Solid,
+    /// Don't draw the cursor at all.
+    Hidden,
+    /// Draw an outlined cursor, used to indicate an unfocused pane.
+    Hollow,
+}
+
 pub struct BlinkManager {
-    blink_interval: Duration,
+    /// The solid-wait/on/off blink cycle timings.
+    timings: BlinkTimings,
+    phase: BlinkPhase,
+    /// How many cycles have completed since blinking last restarted.
+    cycles_elapsed: u64,
+    /// How long the cursor can sit idle before blinking stops and settles solid,
+    /// queried live from the settings. A value of `Duration::ZERO` means
+    /// blinking never times out.
+    blink_timeout: Box<dyn Fn(&App) -> Duration>,
+    /// The last time the user typed or moved the cursor.
+    last_activity: Instant,
     blink_epoch: usize,
     /// Whether the blinking is paused.
     blinking_paused: bool,
+    /// Whether the blinking has timed out due to inactivity.
+    timed_out: bool,
     /// Whether the cursor should be visibly rendered or not.
     visible: bool,
+    /// Whether the editor currently has focus.
+    focused: bool,
     /// The focus handle to use to determine if the cursor should be blinking.
     focus_handle: FocusHandle,
     /// Whether the blinking is enabled in the settings.
     is_enabled: Box<dyn Fn(&App) -> bool>,
+    /// Whether an unfocused editor should render a hollow cursor instead of
+    /// hiding it entirely, per the settings.
+    unfocused_hollow: Box<dyn Fn(&App) -> bool>,
 }
 
 impl BlinkManager {
     pub fn new(
-        blink_interval: Duration,
+        timings: BlinkTimings,
+        blink_timeout: impl Fn(&App) -> Duration + 'static,
         focus_handle: FocusHandle,
         is_enabled: impl Fn(&App) -> bool + 'static,
+        unfocused_hollow: impl Fn(&App) -> bool + 'static,
         window: &mut Window,
         cx: &mut Context<Self>,
     ) -> Self {
@@ -32,23 +90,32 @@ impl BlinkManager {
         .detach();
 
         cx.on_focus(&focus_handle, window, move |this, window, cx| {
+            this.focused = true;
             this.visible = false;
             this.refresh(window, cx);
         })
         .detach();
 
         cx.on_blur(&focus_handle, window, move |this, _window, _cx| {
+            this.focused = false;
             this.visible = false;
         })
         .detach();
 
         Self {
-            blink_interval,
+            timings,
+            phase: BlinkPhase::Waiting,
+            cycles_elapsed: 0,
+            blink_timeout: Box::new(blink_timeout),
+            last_activity: Instant::now(),
             blink_epoch: 0,
             blinking_paused: false,
+            timed_out: false,
             visible: true,
+            focused: true,
             focus_handle,
             is_enabled: Box::new(is_enabled),
+            unfocused_hollow: Box::new(unfocused_hollow),
         }
     }
 
@@ -62,19 +129,38 @@ impl BlinkManager {
     }
 
     pub fn pause_blinking(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.phase = BlinkPhase::Waiting;
+        self.cycles_elapsed = 0;
         self.show_cursor(cx);
 
         let epoch = self.next_blink_epoch();
-        let interval = self.blink_interval;
+        let wait = self.timings.wait;
         cx.spawn_in(window, async move |this, cx| {
-            Timer::after(interval).await;
+            Timer::after(wait).await;
             this.update_in(cx, |this, window, cx| {
+                // The pause itself was the `Waiting` period; go straight to `On`.
+                if epoch == this.blink_epoch {
+                    this.phase = BlinkPhase::On;
+                }
                 this.resume_cursor_blinking(epoch, window, cx)
             })
         })
         .detach();
     }
 
+    /// Records that the user just typed or moved the cursor, resetting the
+    /// idle timer and restarting the blink loop if it had timed out.
+    pub fn report_activity(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        self.last_activity = Instant::now();
+        self.phase = BlinkPhase::Waiting;
+        self.cycles_elapsed = 0;
+        self.timed_out = false;
+        // Show the cursor right away for the solid `Waiting` phase we just
+        // reset to; only an off->on flip in the `On` phase counts as a cycle.
+        self.show_cursor(cx);
+        self.refresh(window, cx);
+    }
+
     fn resume_cursor_blinking(
         &mut self,
         epoch: usize,
@@ -93,15 +179,49 @@ impl BlinkManager {
                 && self.focus_handle.is_focused(window)
                 && !self.blinking_paused
             {
-                self.visible = !self.visible;
+                let blink_timeout = (self.blink_timeout)(cx);
+                if !blink_timeout.is_zero() && self.last_activity.elapsed() >= blink_timeout {
+                    self.timed_out = true;
+                    self.show_cursor(cx);
+                    return;
+                }
+
+                if self.timings.on.is_zero() || self.timings.off.is_zero() {
+                    self.show_cursor(cx);
+                    return;
+                }
+
+                let was_visible = self.visible;
+                self.visible = self.phase != BlinkPhase::Off;
                 cx.notify();
 
+                if !was_visible && self.phase == BlinkPhase::On {
+                    self.cycles_elapsed += 1;
+                    if self.timings.max_cycles != 0
+                        && self.cycles_elapsed >= self.timings.max_cycles
+                    {
+                        self.cycles_elapsed = 0;
+                        self.show_cursor(cx);
+                        return;
+                    }
+                }
+
+                let delay = match self.phase {
+                    BlinkPhase::Waiting => self.timings.wait,
+                    BlinkPhase::On => self.timings.on,
+                    BlinkPhase::Off => self.timings.off,
+                };
+
                 let epoch = self.next_blink_epoch();
-                let interval = self.blink_interval;
                 cx.spawn_in(window, async move |this, cx| {
-                    Timer::after(interval).await;
+                    Timer::after(delay).await;
                     if let Some(this) = this.upgrade() {
                         this.update_in(cx, |this, window, cx| {
+                            this.phase = match this.phase {
+                                BlinkPhase::Waiting => BlinkPhase::On,
+                                BlinkPhase::On => BlinkPhase::Off,
+                                BlinkPhase::Off => BlinkPhase::On,
+                            };
                             this.blink_cursors(epoch, window, cx)
                         })
                         .ok();
@@ -124,4 +244,120 @@ impl BlinkManager {
     pub fn visible(&self) -> bool {
         self.visible
     }
+
+    /// Whether blinking has settled solid due to inactivity (see `blink_timeout`).
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    /// How the cursor should currently be painted, accounting for focus.
+    pub fn cursor_display(&self, cx: &App) -> CursorDisplay {
+        if !self.focused && (self.unfocused_hollow)(cx) {
+            CursorDisplay::Hollow
+        } else if self.visible {
+            CursorDisplay::Solid
+        } else {
+            CursorDisplay::Hidden
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+
+    fn timings(wait_ms: u64, on_ms: u64, off_ms: u64) -> BlinkTimings {
+        BlinkTimings {
+            wait: Duration::from_millis(wait_ms),
+            on: Duration::from_millis(on_ms),
+            off: Duration::from_millis(off_ms),
+            max_cycles: 0,
+        }
+    }
+
+    fn build_manager(timings: BlinkTimings, cx: &mut TestAppContext) -> gpui::Entity<BlinkManager> {
+        cx.add_window(|window, cx| {
+            let focus_handle = cx.focus_handle();
+            window.focus(&focus_handle);
+            cx.new(|cx| {
+                BlinkManager::new(
+                    timings,
+                    |_| Duration::ZERO,
+                    focus_handle,
+                    |_| true,
+                    |_| false,
+                    window,
+                    cx,
+                )
+            })
+        })
+        .unwrap()
+        .root(cx)
+        .unwrap()
+    }
+
+    #[gpui::test]
+    async fn test_phase_transitions(cx: &mut TestAppContext) {
+        let manager = build_manager(timings(10, 10, 10), cx);
+        cx.run_until_parked();
+
+        // Waiting: solid until `wait` elapses.
+        manager.update(cx, |manager, _| assert!(manager.visible()));
+
+        // Waiting -> On: still visible, now ticking on `on`.
+        cx.executor().advance_clock(Duration::from_millis(15));
+        cx.run_until_parked();
+        manager.update(cx, |manager, _| assert!(manager.visible()));
+
+        // On -> Off: hidden after the on-duration.
+        cx.executor().advance_clock(Duration::from_millis(15));
+        cx.run_until_parked();
+        manager.update(cx, |manager, _| assert!(!manager.visible()));
+
+        // Off -> On: visible again after the off-duration.
+        cx.executor().advance_clock(Duration::from_millis(15));
+        cx.run_until_parked();
+        manager.update(cx, |manager, _| assert!(manager.visible()));
+    }
+
+    #[gpui::test]
+    async fn test_zero_duration_disables_blinking(cx: &mut TestAppContext) {
+        let manager = build_manager(timings(0, 0, 10), cx);
+        cx.run_until_parked();
+
+        manager.update(cx, |manager, _| assert!(manager.visible()));
+
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        manager.update(cx, |manager, _| assert!(manager.visible()));
+    }
+
+    #[gpui::test]
+    async fn test_max_cycles_settles_solid(cx: &mut TestAppContext) {
+        let mut timings = timings(10, 10, 10);
+        timings.max_cycles = 1;
+        let manager = build_manager(timings, cx);
+        cx.run_until_parked();
+
+        // Waiting -> On (still the initial solid period, not a cycle yet).
+        cx.executor().advance_clock(Duration::from_millis(15));
+        cx.run_until_parked();
+        manager.update(cx, |manager, _| assert!(manager.visible()));
+
+        // On -> Off.
+        cx.executor().advance_clock(Duration::from_millis(15));
+        cx.run_until_parked();
+        manager.update(cx, |manager, _| assert!(!manager.visible()));
+
+        // Off -> On completes the first cycle, which hits `max_cycles` and
+        // settles the cursor solid instead of scheduling another toggle.
+        cx.executor().advance_clock(Duration::from_millis(15));
+        cx.run_until_parked();
+        manager.update(cx, |manager, _| assert!(manager.visible()));
+
+        cx.executor().advance_clock(Duration::from_secs(1));
+        cx.run_until_parked();
+        manager.update(cx, |manager, _| assert!(manager.visible()));
+    }
 }